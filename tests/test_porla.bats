@@ -276,3 +276,1485 @@ teardown() {
     docker stop rotation_test
     docker rm rotation_test
 }
+
+@test "Shuffle with a JSON field exposes its sub-fields" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "echo '1 {\"lat\": 57.7, \"lon\": 11.9}' | shuffle --field-json-parse payload '{ts} {payload}' '{ts} {payload.lat} {payload.lon}'"
+
+    assert_success
+    assert_line '1 57.7 11.9'
+}
+
+@test "Shuffle skips records lacking a JSON sub-field" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf '1 {\"lat\": 57.7, \"lon\": 11.9}\n2 {\"lat\": 58.1}\n' | shuffle --field-json-parse payload '{ts} {payload}' '{ts} {payload.lat} {payload.lon}'"
+
+    assert_success
+    assert_line '1 57.7 11.9'
+    assert_output --partial 'Could not format line: 2'
+}
+
+@test "Shuffle with a regex input specification" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "echo 'GPGGA,1234' | shuffle --regex '(?P<talker>[A-Z]{2})(?P<type>[A-Z]{3}),(?P<value>\d+)' '{type} {value} {talker}'"
+
+    assert_success
+    assert_line 'GGA 1234 GP'
+
+    run docker run porla "echo 'GPGGA,1234' | shuffle --regex '(?P<talker>[A-Z]{2})' '{talker} {missing}'"
+
+    assert_failure
+    assert_output --partial "references the field 'missing' which is not captured"
+}
+
+@test "Shuffle skips blank output lines with --skip-empty" {
+    bats_require_minimum_version 1.5.0
+
+    # The blank line parses, with empty fields, and renders as a blank line
+
+    run docker run porla "printf 'a 1\n \nb 2\n' | shuffle --regex '(?P<name>\w*) ?(?P<value>\w*)' '{name}{value}' | wc -l"
+
+    assert_success
+    assert_line '3'
+
+    run docker run porla "printf 'a 1\n \nb 2\n' | shuffle --skip-empty --regex '(?P<name>\w*) ?(?P<value>\w*)' '{name}{value}' | wc -l"
+
+    assert_success
+    assert_line '2'
+}
+
+@test "b64 whole-line mode matches the format specification mode" {
+    bats_require_minimum_version 1.5.0
+
+    docker run -v "$TMP_DIR":/recordings porla "b64 --encode < /recordings/test.txt > /recordings/plain.txt"
+    docker run -v "$TMP_DIR":/recordings porla "b64 --encode '{input}' '{output}' < /recordings/test.txt > /recordings/spec.txt"
+
+    assert cmp --silent "$TMP_DIR"/plain.txt "$TMP_DIR"/spec.txt
+
+    docker run -v "$TMP_DIR":/recordings porla "b64 --decode < /recordings/plain.txt > /recordings/decoded.txt"
+
+    assert cmp --silent "$TMP_DIR"/test.txt "$TMP_DIR"/decoded.txt
+}
+
+@test "Fields reports presence counts and inferred types" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf '1 a 2.5\n2 b 3\nbroken\n3 c 4\n' | fields --format json '{id} {name} {value}' | jq -c '[.lines, .parsed, .fields.id.present, .fields.id.type, .fields.name.type, .fields.value.type]'"
+
+    assert_success
+    assert_line '[4,3,3,"int","string","float"]'
+}
+
+@test "Blank and comment lines are skipped without parse errors" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf '# header\n\na 1\n' | jsonify --skip-blank --skip-comment '#' '{name} {value}'"
+
+    assert_success
+    assert_line '{"name": "a", "value": "1"}'
+    refute_output --partial 'Could not parse line'
+}
+
+@test "Jsonify merges a JSON merge patch into every object" {
+    bats_require_minimum_version 1.5.0
+
+    echo '{"source": "sensor_A", "unused": null}' > "$TMP_DIR"/patch.json
+
+    run docker run -v "$TMP_DIR":/recordings porla "echo 'a 1 x' | jsonify --json-merge-patch /recordings/patch.json '{name} {value} {unused}'"
+
+    assert_success
+    assert_line '{"name": "a", "value": "1", "source": "sensor_A"}'
+}
+
+@test "Shuffle with --strict-field-count rejects unknown output fields" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "echo 'a b' | shuffle --strict-field-count '{first} {second}' '{second} {frist}'"
+
+    assert_failure
+    assert_output --partial "references the field 'frist' which is not captured"
+}
+
+@test "Shuffle reads a commented, multi-line input specification from file" {
+    bats_require_minimum_version 1.5.0
+
+    cat > "$TMP_DIR"/spec.txt <<'SPEC'
+# Time of reception
+{timestamp} {talker},   # separated by a comma
+{value}
+SPEC
+
+    run docker run -v "$TMP_DIR":/recordings porla "echo '1700000000.0 GP,42' | shuffle --input-spec-file /recordings/spec.txt '{talker}={value}'"
+
+    assert_success
+    assert_line 'GP=42'
+
+    run docker run porla "echo '1700000000.0 GP,42' | shuffle '{timestamp} {talker},{value}' '{talker}={value}'"
+
+    assert_success
+    assert_line 'GP=42'
+}
+
+@test "Shuffle keeps whitespace separating fields on separate lines of a specification file" {
+    bats_require_minimum_version 1.5.0
+
+    printf '{first}\n {second}\n' > "$TMP_DIR"/spec.txt
+
+    run docker run -v "$TMP_DIR":/recordings porla "echo 'a b' | shuffle --input-spec-file /recordings/spec.txt '{second}-{first}'"
+
+    assert_success
+    assert_line 'b-a'
+}
+
+@test "Jsonify outputs the leading fields of truncated lines with --on-parse-partial" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf '2024 INFO started\n2024 WARN\n' | jsonify --on-parse-partial '{year} {level} {message}'"
+
+    assert_success
+    assert_line '{"year": "2024", "level": "INFO", "message": "started"}'
+    assert_line '{"year": "2024", "level": "WARN"}'
+}
+
+@test "Jsonify writes rows to a SQLite database" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run -v "$TMP_DIR":/recordings porla "printf 'a 1 2.5\nb 2 3.5\n' | jsonify --output-sqlite /recordings/out.db --table readings '{name} {count:d} {value:f}'"
+
+    assert_success
+    assert_exists "$TMP_DIR"/out.db
+
+    run docker run -v "$TMP_DIR":/recordings porla "python3 -c \"import sqlite3; print(sqlite3.connect('/recordings/out.db').execute('SELECT * FROM readings').fetchall())\""
+
+    assert_success
+    assert_line "[('a', 1, 2.5), ('b', 2, 3.5)]"
+}
+
+@test "Jsonify skips records without fields when writing to SQLite" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf 'a\nb\n' | jsonify --discard-fields name --output-sqlite /tmp/out.db '{name}'"
+
+    assert_success
+    assert_output --partial 'Skipping a record without fields for the SQLite table'
+}
+
+@test "Partition keeps lines with the same key in the same file" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run -v "$TMP_DIR":/recordings porla "printf 'a 1\nb 2\na 3\nc 4\n' | partition /recordings/out.log --partitions 2 --key '{key} {}'"
+
+    assert_success
+    assert_exists "$TMP_DIR"/out-000.log
+    assert_exists "$TMP_DIR"/out-001.log
+
+    run bash -c "grep -l '^a ' $TMP_DIR/out-*.log | wc -l"
+
+    assert_success
+    assert_output "1"
+
+    run bash -c "cat $TMP_DIR/out-*.log | wc -l"
+
+    assert_output "4"
+}
+
+@test "Partition skips blank and comment lines without parse errors" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run -v "$TMP_DIR":/recordings porla "printf '# header\n\na 1\n' | partition /recordings/out.log --partitions 1 --key '{key} {}' --skip-blank --skip-comment '#'"
+
+    assert_success
+    refute_output --partial 'Could not parse line'
+
+    run cat "$TMP_DIR"/out-000.log
+
+    assert_output "a 1"
+}
+
+@test "Jsonify rounds floats to a given precision" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "echo 'a 2.12345' | jsonify --float-precision 2 '{name} {value:f}'"
+
+    assert_success
+    assert_line '{"name": "a", "value": 2.12}'
+}
+
+@test "Jsonify preserves the original text of floats" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "echo 'a 1.50' | jsonify --preserve-float-string '{name} {value:f}'"
+
+    assert_success
+    assert_line '{"name": "a", "value": 1.50}'
+}
+
+@test "Timewindow passes lines within inclusive bounds" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf '2024-01-01T00:00:00Z a\n2024-01-01T01:00:00Z b\n2024-01-01T02:00:00Z c\n2024-01-01T03:00:00Z d\n' | timewindow --since 2024-01-01T01:00:00Z --until 2024-01-01T02:00:00Z '{time} {}'"
+
+    assert_success
+    refute_line '2024-01-01T00:00:00Z a'
+    assert_line '2024-01-01T01:00:00Z b'
+    assert_line '2024-01-01T02:00:00Z c'
+    refute_line '2024-01-01T03:00:00Z d'
+}
+
+@test "Timewindow parses relative bounds" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf '%s old\n%s new\n' \$(date -u -d '-2 hours' +%s) \$(date -u +%s) | timewindow --since=-1h '{time} {}' | cut -d ' ' -f 2"
+
+    assert_success
+    refute_line 'old'
+    assert_line 'new'
+}
+
+@test "Jsonify reports the spans of captured fields" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "echo 'vessel 42 ok' | jsonify --with-spans '{name} {speed:d} {}'"
+
+    assert_success
+    assert_line '{"name": {"value": "vessel", "span": [0, 6]}, "speed": {"value": 42, "span": [7, 9]}}'
+}
+
+@test "Jumble permutes lines reproducibly with a fixed seed" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "cmp <(seq 1 100 | jumble --seed 42 --buffer 10) <(seq 1 100 | jumble --seed 42 --buffer 10)"
+
+    assert_success
+
+    run docker run porla "cmp <(seq 1 100) <(seq 1 100 | jumble --seed 42 --buffer 10)"
+
+    assert_failure
+
+    run docker run porla "cmp <(seq 1 100) <(seq 1 100 | jumble --seed 42 --buffer 10 | sort -n)"
+
+    assert_success
+}
+
+@test "Limit applies per-key intervals from a rules file" {
+    bats_require_minimum_version 1.5.0
+
+    printf 'error.* -> 10s\nre:debug\\..+ -> 0\n' > "$TMP_DIR"/rules.txt
+
+    run docker run -v "$TMP_DIR":/recordings porla "printf 'error.a 1\nerror.a 2\ndebug.b 3\ndebug.b 4\n' | limit 10 --rules /recordings/rules.txt --key '{key} {}'"
+
+    assert_success
+    assert_line 'error.a 1'
+    refute_line 'error.a 2'
+    assert_line 'debug.b 3'
+    assert_line 'debug.b 4'
+}
+
+@test "B64 round-trips with the URL safe alphabet" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "echo 'a?>b~' | b64 --encode --url-safe --no-pad"
+
+    assert_success
+    assert_line 'YT8-Yn4'
+
+    run docker run porla "echo 'YT8-Yn4' | b64 --decode --url-safe --no-pad"
+
+    assert_success
+    assert_line 'a?>b~'
+}
+
+@test "B64 fails on input outside of the chosen alphabet" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "echo 'YT8-Yn4=' | b64 --decode"
+
+    assert_failure
+    assert_output --partial 'outside of the standard base64 alphabet'
+}
+
+@test "Filter selects lines with each operator" {
+    bats_require_minimum_version 1.5.0
+
+    input="printf 'a 1\nb 2\nc 3\n'"
+
+    for predicate in 'value:eq:2' 'value:ne:1' 'value:lt:2' 'value:gt:2' 'value:le:2' 'value:ge:2' 'name:contains:b' 'name:matches:^[bc]$'; do
+        run docker run porla "$input | filter --where '$predicate' '{name} {value}' | tr '\n' ' '"
+
+        assert_success
+        case "$predicate" in
+            value:eq:2|name:contains:b) assert_line 'b 2 ' ;;
+            value:ne:1|value:ge:2|name:matches:*) assert_line 'b 2 c 3 ' ;;
+            value:lt:2) assert_line 'a 1 ' ;;
+            value:gt:2) assert_line 'c 3 ' ;;
+            value:le:2) assert_line 'a 1 b 2 ' ;;
+        esac
+    done
+}
+
+@test "Filter inverts the selection and passes the rest through" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run -v "$TMP_DIR":/recordings porla "printf 'a 1\nb 2\nc 3\n' | filter --invert --passthrough --where 'value:gt:1' '{name} {value}' 3> /recordings/rejected.txt"
+
+    assert_success
+    assert_line 'a 1'
+    refute_line 'b 2'
+    refute_line 'c 3'
+
+    run cat "$TMP_DIR"/rejected.txt
+
+    assert_line 'b 2'
+    assert_line 'c 3'
+}
+
+@test "Count writes running counts per key" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf 'a 1\nb 2\na 3\n' | count --key '{key} {}'"
+
+    assert_success
+    assert_line '{"key": "a", "count": 1}'
+    assert_line '{"key": "b", "count": 1}'
+    assert_line '{"key": "a", "count": 2}'
+}
+
+@test "Count writes a sorted summary in batch mode" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf 'a 1\nb 2\na 3\nc 4\nb 5\na 6\n' | count --key '{key} {}' --mode batch | tr '\n' ' '"
+
+    assert_success
+    assert_line '{"key": "a", "count": 3} {"key": "b", "count": 2} {"key": "c", "count": 1} '
+
+    run docker run porla "printf 'a 1\nb 2\na 3\nc 4\nb 5\na 6\n' | count --key '{key} {}' --mode batch --top 2 | tr '\n' ' '"
+
+    assert_success
+    assert_line '{"key": "a", "count": 3} {"key": "b", "count": 2} '
+}
+
+@test "B64 decodes a named field" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "echo '2024 bob aGVsbG8=' | b64 --decode --field payload '{timestamp} {user} {payload}' '{timestamp} {user} {output} ({payload})'"
+
+    assert_success
+    assert_line '2024 bob hello (aGVsbG8=)'
+}
+
+@test "Dedup suppresses all duplicates" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf 'a 1\nb 2\na 3\nc 4\nb 5\n' | dedup --key '{key} {}' | tr '\n' ' '"
+
+    assert_success
+    assert_line 'a 1 b 2 c 4 '
+}
+
+@test "Dedup passes keys evicted from the window again" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf 'a\nb\na\nc\nb\nb\na\n' | dedup --window 2 | tr '\n' ' '"
+
+    assert_success
+    assert_line 'a b c b a '
+}
+
+@test "Dedup only suppresses consecutive duplicates" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf 'a\nb\nb\na\na\nb\n' | dedup --consecutive | tr '\n' ' '"
+
+    assert_success
+    assert_line 'a b a b '
+}
+
+@test "B64 wraps the encoded output" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "head -c 100 /dev/zero | tr '\0' 'x' | b64 --encode --wrap 0 | wc -L"
+
+    assert_success
+    assert_line '136'
+
+    run docker run porla "head -c 100 /dev/zero | tr '\0' 'x' | b64 --encode --wrap 76 | wc -L"
+
+    assert_success
+    assert_line '76'
+
+    run docker run porla "head -c 100 /dev/zero | tr '\0' 'x' | b64 --encode --wrap 500 | wc -l"
+
+    assert_success
+    assert_line '1'
+}
+
+@test "B64 ignores whitespace when decoding" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "echo 'eHh4 eHh4' | b64 --decode"
+
+    assert_success
+    assert_line 'xxxxxx'
+}
+
+@test "B64 handles invalid input according to --on-error" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf 'aGVsbG8=\n!!\naGVsbG8=\n' | b64 --decode --on-error fail"
+
+    assert_failure
+
+    run docker run porla "printf 'aGVsbG8=\n!!\naGVsbG8=\n' | b64 --decode --on-error drop 2>/dev/null | tr '\n' ' '"
+
+    assert_success
+    assert_line 'hello hello '
+
+    run docker run porla "printf 'aGVsbG8=\n!!\naGVsbG8=\n' | b64 --decode --on-error passthrough 2>/dev/null | tr '\n' ' '"
+
+    assert_success
+    assert_line 'hello !! hello '
+}
+
+@test "B64 round-trips binary data" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf '\xff\xfe' | b64 --encode --binary | b64 --decode --binary | od -An -tx1"
+
+    assert_success
+    assert_line ' ff fe'
+}
+
+@test "Dedup counts consecutive duplicates" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf 'a 1\nb 2\nb 3\na 4\na 5\na 6\n' | dedup --consecutive --count --key '{key} {}' | tr '\n' ' '"
+
+    assert_success
+    assert_line '1 a 1 2 b 2 3 a 4 '
+}
+
+@test "Sample passes lines at the given rate on average" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "seq 1 100000 | sample --rate 0.1 | wc -l"
+
+    assert_success
+    count="${lines[-1]}"
+    [ "$count" -gt 9500 ]
+    [ "$count" -lt 10500 ]
+}
+
+@test "Sample is reproducible with a fixed seed" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "cmp <(seq 1 1000 | sample --rate 0.1 --seed 42) <(seq 1 1000 | sample --rate 0.1 --seed 42)"
+
+    assert_success
+}
+
+@test "Sample passes exactly N lines with reservoir sampling" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "seq 1 1000 | sample --exact 7 | sort -u | wc -l"
+
+    assert_success
+    assert_line '7'
+}
+
+@test "Sample accepts the probability as an argument" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "cmp <(seq 1 1000 | sample 0.1 --seed 42) <(seq 1 1000 | sample --rate 0.1 --seed 42)"
+
+    assert_success
+}
+
+@test "Sample passes every Nth line" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "seq 1 10 | sample --every 3 | tr '\n' ' '"
+
+    assert_success
+    assert_line '3 6 9 '
+}
+
+@test "Stats summarizes a numeric field" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf 'x 2\nx 4\nx 4\nx 4\nx 5\nx 5\nx 7\nx 9\n' | stats --field value --percentiles 50 '{name} {value}'"
+
+    assert_success
+    assert_line '{"count": 8, "min": 2.0, "max": 9.0, "mean": 5.0, "stddev": 2.138089935299395, "p50": 4.5}'
+}
+
+@test "Stats writes a summary per window" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "seq 1 7 | stats --field value --window 3 '{value:d}'"
+
+    assert_success
+    assert_line '{"count": 3, "min": 1.0, "max": 3.0, "mean": 2.0, "stddev": 1.0}'
+    assert_line '{"count": 3, "min": 4.0, "max": 6.0, "mean": 5.0, "stddev": 1.0}'
+    refute_output --partial '"count": 1'
+    assert_output --partial 'Dropping an incomplete window of 1 values'
+}
+
+@test "Stats skips values that are not finite" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf '1\nnan\ninf\n3\n' | stats --field value '{value}'"
+
+    assert_success
+    assert_line '{"count": 2, "min": 1.0, "max": 3.0, "mean": 2.0, "stddev": 1.4142135623730951}'
+    assert_output --partial "Could not parse field 'value' as a number: nan"
+}
+
+@test "Window emits a summary when each window closes" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf '0 1\n3 2\n12 5\n11 4\n25 1\n' | window --window 10 --agg sum --timestamp-field time '{time} {value}'"
+
+    assert_success
+    assert_line '{"window_start": 0.0, "window_end": 10.0, "value": 3.0, "count": 2}'
+    assert_line '{"window_start": 10.0, "window_end": 20.0, "value": 9.0, "count": 2}'
+    assert_line '{"window_start": 20.0, "window_end": 30.0, "value": 1.0, "count": 1}'
+}
+
+@test "Window closes the current window on a quiet stream" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "(echo 1; sleep 3; echo 2) | window --window 1 --agg sum '{value}' | timestamp --relative"
+
+    assert_success
+    assert_line --regexp '^[01]\.[0-9]+ \{.*"value": 1\.0, "count": 1\}$'
+}
+
+@test "Window aggregates with each function" {
+    bats_require_minimum_version 1.5.0
+
+    for agg in count sum min max mean; do
+        run docker run porla "printf '0 1\n3 2\n7 6\n' | window --window 10 --agg $agg --timestamp-field time '{time} {value}'"
+
+        assert_success
+        case "$agg" in
+            count) assert_line '{"window_start": 0.0, "window_end": 10.0, "value": 3, "count": 3}' ;;
+            sum) assert_line '{"window_start": 0.0, "window_end": 10.0, "value": 9.0, "count": 3}' ;;
+            min) assert_line '{"window_start": 0.0, "window_end": 10.0, "value": 1.0, "count": 3}' ;;
+            max) assert_line '{"window_start": 0.0, "window_end": 10.0, "value": 6.0, "count": 3}' ;;
+            mean) assert_line '{"window_start": 0.0, "window_end": 10.0, "value": 3.0, "count": 3}' ;;
+        esac
+    done
+}
+
+@test "Window uses the arrival time without a timestamp field" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "(echo 1; sleep 1.5; echo 2) | window --window 1 --agg sum '{value}' | wc -l"
+
+    assert_success
+    assert_line '2'
+}
+
+@test "Line tools give the same output regardless of --flush" {
+    bats_require_minimum_version 1.5.0
+
+    for flush in block 7; do
+        run docker run porla "cmp <(seq 1 100 | jsonify '{value}') <(seq 1 100 | jsonify --flush $flush '{value}')"
+        assert_success
+
+        run docker run porla "cmp <(seq 1 100 | timestamp --epoch | cut -d ' ' -f 2) <(seq 1 100 | timestamp --epoch --flush $flush | cut -d ' ' -f 2)"
+        assert_success
+    done
+
+    run docker run porla "echo 1 | shuffle --flush never '{value}' '{value}'"
+
+    assert_failure
+}
+
+@test "Batch writes batches of exactly N lines" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "seq 1 7 | batch 3"
+
+    assert_success
+    assert_line '["1", "2", "3"]'
+    assert_line '["4", "5", "6"]'
+    assert_line '["7"]'
+
+    run docker run porla "seq 1 7 | batch 3 --drop-partial"
+
+    assert_success
+    assert_line '["4", "5", "6"]'
+    refute_line '["7"]'
+}
+
+@test "Batch writes an incomplete batch after the timeout" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "(echo 1; echo 2; sleep 2; echo 3) | batch 5 --timeout 0.5"
+
+    assert_success
+    assert_line '["1", "2"]'
+    assert_line '["3"]'
+}
+
+@test "Batch parses lines as json" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf '{\"a\": 1}\n[2]\n3\n' | batch 3 --parse-json"
+
+    assert_success
+    assert_line '[{"a": 1}, [2], 3]'
+}
+
+@test "Line tools exit quietly when the reader goes away" {
+    bats_require_minimum_version 1.5.0
+
+    # The input is written to a file first, so that the only broken pipe is the
+    # one between the tool and head, and the output is larger than the pipe buffer
+    run docker run porla "seq 1 10000 > /tmp/input.txt && jsonify '{value}' < /tmp/input.txt | head -n 1"
+
+    assert_success
+    assert_line '{"value": "1"}'
+    refute_output --partial 'BrokenPipeError'
+
+    run docker run porla "seq 1 100000 > /tmp/input.txt && shuffle '{value}' '{value}' < /tmp/input.txt | head -n 1"
+
+    assert_success
+    refute_output --partial 'BrokenPipeError'
+}
+
+@test "Line tools read and write NUL-delimited records with --null" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf 'a\nb\0c\0' | jsonify -0 '{value}' | tr '\0' '|'"
+
+    assert_success
+    assert_output --partial '{"value": "a\nb"}|{"value": "c"}|'
+
+    run docker run porla "printf 'a\0a\0b\0' | limit --null --key '{key}' 10 | tr '\0' ' '"
+
+    assert_success
+    assert_line 'a b '
+}
+
+@test "Line tools read input files in order with --input" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf 'a 1\n' > /tmp/a.txt && printf 'b 2\n' > /tmp/b.txt && echo 'c 3' | shuffle --input /tmp/a.txt --input - --input /tmp/b.txt '{key} {value}' '{value}{key}' | tr '\n' ' '"
+
+    assert_success
+    assert_line '1a 3c 2b '
+}
+
+@test "Hex encodes and decodes" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "echo 'hello' | hex --encode | tee /dev/stderr | hex --decode"
+
+    assert_success
+    assert_line '68656c6c6f'
+    assert_line 'hello'
+
+    run docker run porla "echo 'a ok' | hex --encode --upper '{name} {input}' '{name} {output}'"
+
+    assert_success
+    assert_line 'a 6F6B'
+
+    run docker run porla "printf 'zz\n6869\n' | hex --decode"
+
+    assert_success
+    assert_line 'hi'
+    assert_output --partial 'Could not decode'
+}
+
+@test "Timestamp prepends a custom format" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "echo 'hello' | timestamp --format '%Y-%m-%d %H:%M:%S' | grep -E '^[0-9]{4}-[0-9]{2}-[0-9]{2} [0-9]{2}:[0-9]{2}:[0-9]{2} hello$'"
+
+    assert_success
+}
+
+@test "Url encodes and decodes" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "echo 'a b/c?d=é&x' | url --encode | tee /dev/stderr | url --decode"
+
+    assert_success
+    assert_line 'a%20b%2Fc%3Fd%3D%C3%A9%26x'
+    assert_line 'a b/c?d=é&x'
+
+    run docker run porla "echo 'a b/c' | url --encode --set path"
+
+    assert_success
+    assert_line 'a%20b/c'
+
+    run docker run porla "echo 'q=a b' | url --encode --set query | tee /dev/stderr | url --decode --set query"
+
+    assert_success
+    assert_line 'q%3Da+b'
+    assert_line 'q=a b'
+
+    run docker run porla "printf '%%zz\nok%%21\n' | url --decode"
+
+    assert_success
+    assert_line 'ok!'
+    assert_output --partial 'Malformed escape'
+}
+
+@test "Timestamp uses the local timezone with --local" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run -e TZ=UTC-02 porla "echo 'hello' | timestamp --rfc3339 --local"
+
+    assert_success
+    assert_output --regexp '\+02:00 hello'
+
+    run docker run -e TZ=UTC-02 porla "echo 'hello' | timestamp --rfc3339"
+
+    assert_success
+    assert_output --regexp '\+00:00 hello'
+
+    run docker run porla "timestamp --epoch --local"
+
+    assert_failure
+}
+
+@test "Timestamp puts the stamp according to --template" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "echo 'hello' | timestamp --epoch --template 'INFO {ts} LINE={}'"
+
+    assert_success
+    assert_output --regexp 'INFO [0-9]+\.[0-9]{6} LINE=hello'
+}
+
+@test "Csvify writes quoted CSV rows" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf '1 a,b say \"hi\"\n' | csvify '{id:d} {name} {rest}'"
+
+    assert_success
+    assert_line 'id,name,rest'
+    assert_line '1,"a,b","say ""hi"""'
+
+    run docker run porla "echo '1 a b' | csvify --no-header --delimiter ';' --fields rest,id '{id:d} {name} {rest}'"
+
+    assert_success
+    assert_line 'b;1'
+    refute_line 'rest;id'
+}
+
+@test "Timestamp prepends elapsed seconds with --relative and --delta" {
+    bats_require_minimum_version 1.5.0
+
+    # Elapsed seconds since the start never decrease
+    run docker run porla "seq 1 100 | timestamp --relative | cut -d ' ' -f 1 | sort -c -g"
+
+    assert_success
+
+    run docker run porla "(echo a; sleep 1; echo b) | timestamp --delta | cut -d ' ' -f 1 | tr '\n' ' '"
+
+    assert_success
+    assert_output --regexp '0\.[0-9]{6} 1\.[0-9]{6} '
+}
+
+@test "Tsv parses tab-separated columns into json" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf 'x\ty\na\tb\nc\nd\te\tf\n' | tsv --columns x,y --skip-header 1"
+
+    assert_success
+    assert_line '{"x": "a", "y": "b"}'
+    assert_line '{"x": "c", "y": null}'
+    assert_line '{"x": "d", "y": "e"}'
+    refute_line '{"x": "x", "y": "y"}'
+
+    run docker run porla "printf 'a\tb\nc\nd\te\tf\n' | tsv --columns x,y --strict"
+
+    assert_success
+    assert_line '{"x": "a", "y": "b"}'
+    refute_line '{"x": "c", "y": null}'
+    refute_line '{"x": "d", "y": "e"}'
+}
+
+@test "Timestamp renders the epoch with --precision" {
+    bats_require_minimum_version 1.5.0
+
+    for precision in s:10 ms:13 ns:19; do
+        run docker run porla "echo 'hello' | timestamp --epoch --precision ${precision%:*}"
+
+        assert_success
+        assert_output --regexp "(^|[^0-9])[0-9]{${precision#*:}} hello"
+    done
+
+    run docker run porla "echo 'hello' | timestamp --epoch"
+
+    assert_success
+    assert_output --regexp '[0-9]{10}\.[0-9]{6} hello'
+}
+
+@test "Timestamp logs each line with --log-level DEBUG" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "echo 'hello' | timestamp --epoch --log-level DEBUG"
+
+    assert_success
+    assert_output --partial 'DEBUG timestamp hello'
+}
+
+@test "Alert writes an alert only when the threshold is crossed" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf '1\n12\n15\n9.5\n8\n12\n' | alert --field value --above 10 --hysteresis 1 '{value:g}' | jq -c '[(.value | floor), .direction]' | tr '\n' ' '"
+
+    assert_success
+    assert_line '[12,"rising"] [8,"falling"] [12,"rising"] '
+
+    run docker run porla "printf '5\n-1\n-2\n2\n' | alert --field value --below 0 '{value:g}' | jq -c '[(.value | floor), .direction]' | tr '\n' ' '"
+
+    assert_success
+    assert_line '[-1,"falling"] [2,"rising"] '
+
+    run docker run porla "printf 'a 12\nb 12\na 13\nb 1\n' | alert --field value --above 10 --per-key id '{id} {value:g}' | jq -c '[.key, .direction]' | tr '\n' ' '"
+
+    assert_success
+    assert_line '["a","rising"] ["b","rising"] ["b","falling"] '
+}
+
+@test "Limit allows bursts with --burst" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "seq 1 10 | limit --burst 3 10 | tr '\n' ' '"
+
+    assert_success
+    assert_line '1 2 3 '
+}
+
+@test "Rate computes the rate of a counter" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf '0 100\n1 150\n3 250\n' | rate --field packets --timestamp-field time '{time:d} {packets:d}'"
+
+    assert_success
+    assert_line '{"time": 1, "packets": 150, "rate": 50.0, "delta": 50.0, "dt": 1.0}'
+    assert_line '{"time": 3, "packets": 250, "rate": 50.0, "delta": 100.0, "dt": 2.0}'
+
+    run docker run porla "printf '0 250\n1 4\n' | rate --field packets --timestamp-field time --max 255 '{time:d} {packets:d}'"
+
+    assert_success
+    assert_line '{"time": 1, "packets": 4, "rate": 10.0, "delta": 10.0, "dt": 1.0}'
+
+    run docker run porla "printf '0 0\n1 10\n2 30\n3 60\n' | rate --field packets --timestamp-field time --smoothing ewma --alpha 0.5 '{time:d} {packets:d}' | grep -o '\"rate\": [0-9.]*' | tr '\n' ' '"
+
+    assert_success
+    assert_line '"rate": 10.0 "rate": 15.0 "rate": 22.5 '
+}
+
+@test "Limit forgets the least recently seen keys with --max-keys" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf 'a\nb\na\n' | limit --key '{key}' 10 | tr '\n' ' '"
+
+    assert_success
+    assert_line 'a b '
+
+    # With room for a single key, 'a' is forgotten when 'b' is seen
+    run docker run porla "printf 'a\nb\na\n' | limit --key '{key}' --max-keys 1 10 | tr '\n' ' '"
+
+    assert_success
+    assert_line 'a b a '
+}
+
+@test "Limit delays lines instead of dropping them with --delay" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "seq 1 3 | limit --delay 1 | timestamp --relative | cut -d ' ' -f 1 | tr '\n' ' '"
+
+    assert_success
+    assert_output --regexp '0\.[0-9]{6} 1\.[0-9]{6} 2\.[0-9]{6} '
+}
+
+@test "Accumulate writes the running sum of a field" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf 'a 1 run\nb 2 run\na 3 run\na 4 start\n' | accumulate --field energy --per-key id --reset-on state:start '{id} {energy:g} {state}'"
+
+    assert_success
+    assert_line '{"id": "a", "energy": 1.0, "state": "run", "energy_cumulative": 1.0}'
+    assert_line '{"id": "b", "energy": 2.0, "state": "run", "energy_cumulative": 2.0}'
+    assert_line '{"id": "a", "energy": 3.0, "state": "run", "energy_cumulative": 4.0}'
+    assert_line '{"id": "a", "energy": 4.0, "state": "start", "energy_cumulative": 4.0}'
+
+    run docker run porla "printf '9007199254740992\n1\n' | accumulate --field energy '{energy:d}'"
+
+    assert_success
+    assert_output --partial 'losing precision'
+}
+
+@test "Http-post posts each line to an endpoint" {
+    bats_require_minimum_version 1.5.0
+
+    # socat captures the raw request, without ever responding
+    run docker run porla "socat -u TCP-LISTEN:8080,reuseaddr OPEN:/tmp/request.txt,creat & sleep 1; echo 'hello' | http-post --url http://localhost:8080/ --header 'X-Token: secret' --timeout 1; sleep 1; cat /tmp/request.txt"
+
+    assert_success
+    assert_output --partial 'POST / HTTP/1.1'
+    assert_output --partial 'Content-Type: text/plain'
+    assert_output --partial 'X-Token: secret'
+    assert_output --partial 'hello'
+}
+
+@test "Http-post retries requests failing with a server error" {
+    bats_require_minimum_version 1.5.0
+
+    # http.server does not implement POST and responds with 501
+    run docker run porla "python3 -m http.server 8080 >/dev/null 2>&1 & sleep 1; echo 'hello' | http-post --url http://localhost:8080/ --retry 1"
+
+    assert_success
+    assert_output --partial 'failed with HTTP 501'
+    assert_output --partial 'after 2 failed attempts'
+}
+
+@test "Limit allows at most N lines per window with --max and --per" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf 'a\na\nb\na\nb\nb\n' | limit --key '{key}' --max 2 --per 10 | tr '\n' ' '"
+
+    assert_success
+    assert_line 'a a b b '
+}
+
+@test "Multiline joins continuation lines into a single record" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf 'INFO start\nERROR boom\n\tat a.b(C.java:1)\nCaused by: z\n\tat f\nINFO done\n' | multiline --continuation '^(\s|Caused by:)' --join-with ' | '"
+
+    assert_success
+    assert_line 'INFO start'
+    assert_line $'ERROR boom | \tat a.b(C.java:1) | Caused by: z | \tat f'
+    assert_line 'INFO done'
+
+    run docker run porla "seq 1 5 | multiline --start '^1$' --max-lines 2 --join-with ','"
+
+    assert_success
+    assert_line '1,2'
+    assert_line '3,4'
+    assert_line '5'
+}
+
+@test "Limit combines several fields into the key with --key-fields" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf 'h1 /a x\nh1 /b y\nh1 /a z\n' | limit --key '{host} {path} {}' --key-fields host,path 10"
+
+    assert_success
+    assert_line 'h1 /a x'
+    assert_line 'h1 /b y'
+    refute_line 'h1 /a z'
+}
+
+@test "Prefix prepends and appends strings to each line" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run -e SITE=gothenburg porla "echo 'hello' | prefix --prepend 'site={env:SITE}' --append 'end'"
+
+    assert_success
+    assert_line 'site=gothenburg hello end'
+
+    run docker run porla "echo 'hello' | prefix --prepend '{timestamp}' --separator ' | '"
+
+    assert_success
+    assert_output --regexp '[0-9]{4}-[0-9]{2}-[0-9]{2}T[0-9:.]+\+00:00 \| hello'
+}
+
+@test "Limit writes statistics to stderr with --stats" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf 'a\nb\na\n' | limit --key '{key}' --stats 60 10 2>&1 >/dev/null"
+
+    assert_success
+    assert_line 'limit stats: seen=3 passed=2 dropped=1 keys=2'
+}
+
+@test "Syslog formats lines as RFC 5424 messages" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "echo '12:00 hello world' | syslog --facility local0 --severity err --hostname host1 --app-name app --proc-id 42 '{time} {message}'"
+
+    assert_success
+    assert_output --regexp '<131>1 [0-9]{4}-[0-9]{2}-[0-9]{2}T[0-9]{2}:[0-9]{2}:[0-9]{2}\.[0-9]{3}Z host1 app 42 - - hello world'
+
+    run docker run porla "socat -u UDP-RECV:5514 OPEN:/tmp/udp.txt,creat & sleep 1; echo 'hello' | syslog --udp localhost:5514 >/dev/null; sleep 1; cat /tmp/udp.txt"
+
+    assert_success
+    assert_output --regexp '<14>1 [^ ]+ [^ ]+ porla - - - hello'
+}
+
+@test "Limit compares event times with --time-field" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf 'a 2024-01-01T00:00:00Z\na 2024-01-01T00:00:05Z\na 2024-01-01T00:00:20Z\n' | limit --key '{key} {time}' --time-field time 10"
+
+    assert_success
+    assert_line 'a 2024-01-01T00:00:00Z'
+    refute_line 'a 2024-01-01T00:00:05Z'
+    assert_line 'a 2024-01-01T00:00:20Z'
+}
+
+@test "Limit passes a line without a valid time without affecting later event times" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf 'a bad\na 2024-01-01T00:01:00Z\na 2024-01-01T00:05:00Z\na 2024-01-01T00:05:30Z\n' | limit --key '{key} {time}' --time-field time 60"
+
+    assert_success
+    assert_line 'a bad'
+    assert_line --partial 'Passing line without a valid time: a bad'
+    assert_line 'a 2024-01-01T00:01:00Z'
+    assert_line 'a 2024-01-01T00:05:00Z'
+    refute_line 'a 2024-01-01T00:05:30Z'
+}
+
+@test "Limit writes the latest line of each burst with --debounce" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "(printf 'a 1\na 2\n'; sleep 1; printf 'a 3\n') | limit --debounce --key '{key} {}' 0.5"
+
+    assert_success
+    refute_line 'a 1'
+    assert_line 'a 2'
+    assert_line 'a 3'
+}
+
+@test "Influxdb formats lines as line protocol" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "echo '2024-01-01T00:00:00Z s1 21.5 3 ok' | influxdb --measurement weather --tag-fields sensor --timestamp-field ts '{ts} {sensor} {temperature:g} {count:d} {status}'"
+
+    assert_success
+    assert_line 'weather,sensor=s1 temperature=21.5,count=3i,status="ok" 1704067200000000000'
+}
+
+@test "Influxdb escapes tags and string fields" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "echo '1704067200|a b,c=d|say \"hi\"' | influxdb --measurement 'my data' --tag-fields tag --timestamp-field ts --precision s '{ts}|{tag}|{text}'"
+
+    assert_success
+    assert_line 'my\ data,tag=a\ b\,c\=d text="say \"hi\"" 1704067200'
+}
+
+@test "Prometheus-push pushes metrics in the text format" {
+    bats_require_minimum_version 1.5.0
+
+    # socat captures the raw request, without ever responding
+    run docker run porla "socat -u TCP-LISTEN:8080,reuseaddr OPEN:/tmp/request.txt,creat & sleep 1; echo '21.5' | prometheus-push --gateway http://localhost:8080 --job sensors --metric temperature:temperature_celsius:gauge --label site:harbour --timeout 1 '{temperature:g}'; sleep 1; cat /tmp/request.txt"
+
+    assert_success
+    assert_output --partial 'PUT /metrics/job/sensors HTTP/1.1'
+    assert_line '# TYPE temperature_celsius gauge'
+    assert_line 'temperature_celsius{site="harbour"} 21.5'
+}
+
+@test "Filter passes lines matching the specification without --where" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf 'ERROR disk full\njunk\n' | filter '{level} {message}'"
+
+    assert_success
+    assert_line 'ERROR disk full'
+    refute_line 'junk'
+    refute_output --partial 'Could not parse line'
+}
+
+@test "Filter passes lines not matching the specification with --invert and without --where" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf 'ERROR disk full\njunk\n' | filter --invert '{level} {message}'"
+
+    assert_success
+    assert_line 'junk'
+    refute_line 'ERROR disk full'
+}
+
+@test "Uuid prepends a unique UUID to each line" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf 'a\na\n' | uuid | cut -d ' ' -f 1 | sort -u | grep -cE '^[0-9a-f]{8}-[0-9a-f]{4}-4[0-9a-f]{3}-[89ab][0-9a-f]{3}-[0-9a-f]{12}$'"
+
+    assert_success
+    assert_line '2'
+}
+
+@test "Uuid adds a time-ordered UUID field with --json" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "echo '{\"value\": 1}' | uuid --json --version 7 --field-name id | jq -r '.value, (.id | test(\"^[0-9a-f]{8}-[0-9a-f]{4}-7[0-9a-f]{3}-[89ab][0-9a-f]{3}-[0-9a-f]{12}$\"))'"
+
+    assert_success
+    assert_line '1'
+    assert_line 'true'
+}
+
+@test "Count writes the summary so far to stderr with --interval" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "(printf 'a\nb\na\n'; sleep 2; printf 'c\n') | count --mode batch --top 1 --interval 1 2>&1 >/dev/null"
+
+    assert_success
+    assert_line '{"key": "a", "count": 2}'
+}
+
+@test "Count writes the summary with --interval while no lines arrive" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "(printf 'a\nb\na\n'; sleep 3) | count --mode batch --top 1 --interval 1 2>&1 >/dev/null | wc -l"
+
+    assert_success
+    assert_line --regexp '^[23]$'
+}
+
+@test "Unjson formats json objects according to an output specification" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf '{\"level\": \"INFO\", \"message\": \"hi\", \"meta\": {\"host\": \"h1\"}}\nnot json\n' | unjson '{meta.host} {level}: {message}'"
+
+    assert_success
+    assert_line 'h1 INFO: hi'
+    assert_output --partial 'Could not parse line as json: not json'
+}
+
+@test "Limit smears out simultaneously eligible keys with --spread" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf 'a\nb\nc\n' | limit --key '{key}' --delay --spread 1 --seed 1 10 | timestamp --epoch --precision ms | cut -d ' ' -f 1 | sort -u | awk 'NR == 1 {first = \$1} {last = \$1} END {print NR, (last - first >= 500 ? \"spread\" : \"burst\")}'"
+
+    assert_success
+    assert_line '3 spread'
+}
+
+@test "Limit writes simultaneously eligible keys at their own offsets with --spread" {
+    bats_require_minimum_version 1.5.0
+
+    # The offsets drawn with seed 1 are about 0.13, 0.85, 0.76 and 0.26 seconds
+    run docker run porla "printf 'a\nb\nc\nd\n' | limit --key '{key}' --delay --spread 1 --seed 1 10 | timestamp --relative | awk '{print \$2, (\$1 < 1.5 ? \"in time\" : \"late\")}' | paste -sd ,"
+
+    assert_success
+    assert_line 'a in time,d in time,c in time,b in time'
+
+    # The offset of the key, about 0.31 seconds, applies once rather than per line
+    run docker run porla "seq 1 5 | limit --delay --spread 0.5 --seed 5 0 | timestamp --relative | awk 'END {print NR, (\$1 < 1 ? \"in time\" : \"late\")}'"
+
+    assert_success
+    assert_line '5 in time'
+}
+
+@test "Limit rejects a negative --spread" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "limit --delay --spread=-1 10 < /dev/null"
+
+    assert_failure
+    assert_output --partial '--spread must not be negative'
+}
+
+@test "Limit forgets the statistics of evicted keys with --max-keys" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "seq 1 100 | limit --key '{key}' --max-keys 3 --key-stats-output /tmp/stats.json 10 > /dev/null; jq length /tmp/stats.json"
+
+    assert_success
+    assert_line '3'
+}
+
+@test "Limit writes held back lines of evicted keys early with --debounce and --max-keys" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "(printf 'a\nb\nc\n'; sleep 2) | limit --key '{key}' --debounce --max-keys 2 10 | timestamp --relative"
+
+    assert_success
+    assert_line --regexp '^0\.[0-9]+ a$'
+}
+
+@test "Jsonify leaves discarded fields out of the json object" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "echo 'x sep 1' | jsonify --discard-fields anchor '{name} {anchor} {value:d}'"
+
+    assert_success
+    assert_line '{"name": "x", "value": 1}'
+}
+
+@test "Limit writes per-key statistics with --key-stats-output" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf 'a\na\nb\n' | limit --key '{key}' --key-stats-output /tmp/stats.json 10 > /dev/null; jq -c 'map({key, passed, dropped, first_seen: (.first_seen | type), last_seen: (.last_seen | type)})' /tmp/stats.json"
+
+    assert_success
+    assert_line '[{"key":"a","passed":1,"dropped":1,"first_seen":"string","last_seen":"string"},{"key":"b","passed":1,"dropped":0,"first_seen":"string","last_seen":"string"}]'
+}
+
+@test "Limit rewrites per-key statistics atomically on SIGTERM" {
+    bats_require_minimum_version 1.5.0
+
+    cat > "$TMP_DIR"/sigterm.sh <<'SCRIPT'
+mkfifo /tmp/input
+limit --key '{key}' --key-stats-output /tmp/stats.jsonl 10 < /tmp/input > /dev/null &
+pid=$!
+exec 3> /tmp/input
+printf 'a\na\nb\n' >&3
+sleep 1
+kill -TERM "$pid"
+wait "$pid"
+jq -c '{key, passed, dropped}' /tmp/stats.jsonl
+ls /tmp/stats.jsonl*
+SCRIPT
+
+    run docker run -v "$TMP_DIR":/recordings porla "bash /recordings/sigterm.sh"
+
+    assert_success
+    assert_line '{"key":"a","passed":1,"dropped":1}'
+    assert_line '{"key":"b","passed":1,"dropped":0}'
+    refute_line '/tmp/stats.jsonl.tmp'
+}
+
+@test "Shuffle reloads its config on SIGHUP with --watch-config" {
+    bats_require_minimum_version 1.5.0
+
+    cat > "$TMP_DIR"/reload.sh <<'SCRIPT'
+echo '{"input_spec": "{a} {b}", "output_spec": "first={a}"}' > /tmp/config.json
+mkfifo /tmp/input
+shuffle --watch-config /tmp/config.json < /tmp/input &
+pid=$!
+exec 3> /tmp/input
+echo 'x y' >&3
+sleep 1
+echo '{"input_spec": "{a} {b}", "output_spec": "second={b}"}' > /tmp/config.json
+kill -HUP "$pid"
+sleep 1
+echo 'x y' >&3
+sleep 1
+echo 'not a config' > /tmp/config.json
+kill -HUP "$pid"
+sleep 1
+echo 'x z' >&3
+exec 3>&-
+wait "$pid"
+SCRIPT
+
+    run docker run -v "$TMP_DIR":/recordings porla "bash /recordings/reload.sh"
+
+    assert_success
+    assert_line 'first=x'
+    assert_line 'second=y'
+    assert_output --partial 'keeping the current one'
+    assert_line 'second=z'
+}
+
+@test "Shuffle reads all of stdin before processing with --buffer-stdin" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "(echo a; sleep 3; echo b) | shuffle --buffer-stdin '{x}' 'got {x}' | timestamp --relative"
+
+    assert_success
+    assert_line --regexp '^[2-9]\.[0-9]+ got a$'
+
+    run docker run porla "(echo a; sleep 3; echo b) | shuffle --buffer-stdin --buffer-stdin-max 1 '{x}' 'got {x}' | timestamp --relative"
+
+    assert_success
+    assert_line --regexp '^[01]\.[0-9]+ got a$'
+}
+
+@test "Tools log a trace of each line with --trace" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "echo 'a 1' | jsonify --log-level DEBUG --trace '{name} {value}' 2>&1 >/dev/null"
+
+    assert_success
+    assert_output --partial "TRACE: input: 'a 1\n'"
+    assert_output --partial "TRACE: fields: {'name': 'a', 'value': '1'}"
+    assert_output --partial 'TRACE: output: '
+
+    run docker run porla "echo a | prefix --prepend x --log-level DEBUG --trace 2>&1 >/dev/null"
+
+    assert_success
+    assert_output --partial "TRACE: input: 'a\n'"
+    assert_output --partial "TRACE: output: 'x a'"
+
+    run docker run porla "echo a | prefix --prepend x --trace 2>&1 >/dev/null"
+
+    assert_success
+    refute_output --partial 'TRACE:'
+}
+
+@test "Watchdog exits with a failure when no input arrives within the idle period" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "(echo a; sleep 3; echo b) | watchdog --idle 1 --error-line '{\"error\": \"idle\"}'"
+
+    assert_failure 1
+    assert_line 'a'
+    assert_line '{"error": "idle"}'
+    assert_line --partial 'No input arrived within 1 seconds'
+    refute_line 'b'
+}
+
+@test "Watchdog passes a stream through when input arrives in time" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "(echo a; sleep 1; echo b; sleep 1; echo c) | watchdog --idle 2"
+
+    assert_success
+    assert_line 'a'
+    assert_line 'b'
+    assert_line 'c'
+
+    run docker run porla "(echo a; sleep 1; echo b; sleep 1; echo c; sleep 1; echo d) | watchdog --idle 2 --total 2"
+
+    assert_failure 1
+    assert_line 'b'
+    assert_line --partial 'The input did not end within 2 seconds'
+    refute_line 'd'
+}
+
+@test "Zipjoin merges lines with the same key from two streams" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "mkfifo /tmp/right; (sleep 1; echo 'a 50'; echo 'c 70') > /tmp/right & printf 'a 20\nb 21\n' | zipjoin --left-spec '{device} {temperature:d}' --right-spec '{device} {humidity:d}' --left-key device --right-key device --right-input /tmp/right"
+
+    assert_success
+    assert_line '{"device": "a", "temperature": 20, "humidity": 50}'
+    assert_line '{"device": "b", "temperature": 21, "humidity": null}'
+    refute_output --partial '"c"'
+}
+
+@test "Zipjoin writes left lines without a match after --timeout" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "mkfifo /tmp/right; (sleep 3; echo 'a 50') > /tmp/right & printf 'a 20\n' | zipjoin --left-spec '{device} {temperature:d}' --right-spec '{device} {humidity:d}' --left-key device --right-key device --right-input /tmp/right --timeout 1"
+
+    assert_success
+    assert_line '{"device": "a", "temperature": 20, "humidity": null}'
+    refute_output --partial '"humidity": 50'
+}
+
+@test "Zipjoin writes a replaced left line with the same key without a match" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "mkfifo /tmp/right; (sleep 1; echo 'a 50') > /tmp/right & printf 'a 20\na 21\n' | zipjoin --left-spec '{device} {temperature:d}' --right-spec '{device} {humidity:d}' --left-key device --right-key device --right-input /tmp/right"
+
+    assert_success
+    assert_line '{"device": "a", "temperature": 20, "humidity": null}'
+    assert_line '{"device": "a", "temperature": 21, "humidity": 50}'
+    assert_line --partial "A left line with the key 'a' arrived before a match"
+}
+
+@test "Digest replaces a field with its digest" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "echo '1 dev1 20' | digest --field device '{t} {device} {v}' '{t} {device} {v}'"
+
+    assert_success
+    assert_line '1 cf4b9c1f5eb31deb9ea41f56faa757b68be9cab8b73f463229df17036bdfa13e 20'
+
+    run docker run porla "echo '1 dev1 20' | digest --field device --algo sha1 '{t} {device} {v}' '{device}'"
+
+    assert_success
+    assert_line '3b42553c4e3241e8f3f8fbc19a69fa2f95708a9d'
+
+    run docker run porla "echo '1 dev1 20' | digest --field device --algo md5 '{t} {device} {v}' '{device}'"
+
+    assert_success
+    assert_line 'cea099a8f5ac3e289e317d461beb9261'
+
+    run docker run porla "echo '1 dev1 20' | digest --field device --algo blake2b '{t} {device} {v}' '{device}' | cmp - <(printf dev1 | b2sum | cut -d ' ' -f 1)"
+
+    assert_success
+}
+
+@test "Digest computes an HMAC into a separate output field" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "echo '1 dev1 20' | digest --field device --output-field id --hmac-key 736563726574 '{t} {device} {v}' '{device} {id}'"
+
+    assert_success
+    assert_line 'dev1 c70f7f32959d0d1be9650a2b2e69f5e1222da7a56114c0872f17cac4838ce407'
+}
+
+@test "Enumerate numbers each line" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf 'a\nb\nc\n' | enumerate --start 10 --step 5"
+
+    assert_success
+    assert_line '10 a'
+    assert_line '15 b'
+    assert_line '20 c'
+
+    run docker run porla "printf 'a\nb\n' | enumerate --start 255 --format hex"
+
+    assert_success
+    assert_line 'ff a'
+    assert_line '100 b'
+
+    run docker run porla "printf 'a\n' | enumerate --format padded:4"
+
+    assert_success
+    assert_line '0001 a'
+}
+
+@test "Enumerate resets the sequence number on matching lines" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf 'x\nBEGIN\ny\nBEGIN\n' | enumerate --reset-on '^BEGIN' | paste -sd ,"
+
+    assert_success
+    assert_line '1 x,1 BEGIN,2 y,1 BEGIN'
+}
+
+@test "Enumerate writes json objects with --json" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf 'a b\n' | enumerate --json"
+
+    assert_success
+    assert_line '{"seq": 1, "line": "a b"}'
+}
+
+@test "Sortby sorts lines by a field and writes unparsed lines last" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf 'b;10;x\na;9;y\nbroken\nc;nan;z\nd;2;w\n' | sortby --numeric --key v '{n};{v};{t}' 2>/dev/null | paste -sd ,"
+
+    assert_success
+    assert_line 'd;2;w,a;9;y,b;10;x,c;nan;z,broken'
+
+    run docker run porla "printf 'b;10;x\na;9;y\nbroken\nd;2;w\n' | sortby --numeric --desc --key v '{n};{v};{t}'"
+
+    assert_success
+    assert_line --partial 'Could not parse line: broken'
+    assert_line 'b;10;x'
+
+    run docker run porla "printf 'b;10;x\na;9;y\nd;2;w\n' | sortby --key v '{n};{v};{t}' | paste -sd ,"
+
+    assert_success
+    assert_line 'b;10;x,d;2;w,a;9;y'
+}
+
+@test "Sortby keeps the order of equal values with --stable" {
+    bats_require_minimum_version 1.5.0
+
+    run docker run porla "printf 'z 1\na 1\nm 0\n' | sortby --stable --key v '{n} {v}' | paste -sd ,"
+
+    assert_success
+    assert_line 'm 0,z 1,a 1'
+
+    run docker run porla "printf 'z 1\na 1\nm 0\n' | sortby --key v '{n} {v}' | paste -sd ,"
+
+    assert_success
+    assert_line 'm 0,a 1,z 1'
+}